@@ -0,0 +1,199 @@
+//! Thin wrappers around the generated `abi::peggy`/`abi::test_erc20`
+//! bindings, with a hand-rolled `ethereum_peggy`/`web30` fallback for builds
+//! that ran without `solidity/artifacts` present (and so never generated
+//! the typed bindings in the first place, see `abi::mod`). Callers use
+//! these instead of picking between `ethereum_peggy::utils` and `web30`
+//! directly, so the typed path is an actual, exercised consumer rather than
+//! dead code.
+
+use clarity::Address as EthAddress;
+use clarity::PrivateKey as EthPrivateKey;
+use clarity::Uint256;
+use web30::client::Web3;
+
+#[cfg(any(has_peggy_abi, has_test_erc20_abi))]
+use {
+    ethers::providers::{Http, Provider},
+    std::convert::TryFrom,
+    std::sync::Arc,
+};
+
+#[cfg(has_peggy_abi)]
+use super::peggy::Peggy;
+
+#[cfg(has_test_erc20_abi)]
+use super::test_erc20::TestErc20;
+
+#[cfg(any(has_peggy_abi, has_test_erc20_abi))]
+fn to_ethers_address(address: EthAddress) -> ethers::types::Address {
+    ethers::types::Address::from_slice(address.as_bytes())
+}
+
+#[cfg(has_peggy_abi)]
+fn from_ethers_address(address: ethers::types::Address) -> EthAddress {
+    EthAddress::from_slice(address.as_bytes()).expect("ethers Address is not 20 bytes")
+}
+
+#[cfg(any(has_peggy_abi, has_test_erc20_abi))]
+fn from_ethers_uint256(value: ethers::types::U256) -> Uint256 {
+    let mut buf = [0u8; 32];
+    value.to_big_endian(&mut buf);
+    Uint256::from_bytes_be(&buf)
+}
+
+#[cfg(any(has_peggy_abi, has_test_erc20_abi))]
+fn provider(node_url: &str) -> Arc<Provider<Http>> {
+    Arc::new(Provider::<Http>::try_from(node_url).expect("Invalid Ethereum node url"))
+}
+
+#[cfg(has_peggy_abi)]
+fn peggy_contract(node_url: &str, peggy_address: EthAddress) -> Peggy<Provider<Http>> {
+    Peggy::new(to_ethers_address(peggy_address), provider(node_url))
+}
+
+#[cfg(has_test_erc20_abi)]
+fn test_erc20_contract(node_url: &str, token: EthAddress) -> TestErc20<Provider<Http>> {
+    TestErc20::new(to_ethers_address(token), provider(node_url))
+}
+
+/// Returns the Peggy contract's current valset nonce.
+pub async fn valset_nonce(
+    node_url: &str,
+    peggy_address: EthAddress,
+    caller_address: EthAddress,
+    web30: &Web3,
+) -> Uint256 {
+    #[cfg(has_peggy_abi)]
+    {
+        let _ = caller_address;
+        from_ethers_uint256(
+            peggy_contract(node_url, peggy_address)
+                .state_last_valset_nonce()
+                .call()
+                .await
+                .expect("Failed to call Peggy.state_last_valset_nonce"),
+        )
+    }
+    #[cfg(not(has_peggy_abi))]
+    {
+        let _ = node_url;
+        ethereum_peggy::utils::get_valset_nonce(peggy_address, caller_address, web30)
+            .await
+            .expect("Failed to get valset nonce")
+    }
+}
+
+/// Returns the Peggy contract's current valset checkpoint hash.
+pub async fn checkpoint(
+    node_url: &str,
+    peggy_address: EthAddress,
+    caller_address: EthAddress,
+    web30: &Web3,
+) -> [u8; 32] {
+    #[cfg(has_peggy_abi)]
+    {
+        let _ = caller_address;
+        peggy_contract(node_url, peggy_address)
+            .state_last_valset_checkpoint()
+            .call()
+            .await
+            .expect("Failed to call Peggy.state_last_valset_checkpoint")
+    }
+    #[cfg(not(has_peggy_abi))]
+    {
+        let _ = node_url;
+        ethereum_peggy::utils::get_checkpoint(peggy_address, caller_address, web30)
+            .await
+            .expect("Failed to get checkpoint")
+    }
+}
+
+/// Returns the ERC20 address the Peggy contract deployed for `denom`, if
+/// any.
+pub async fn erc20_for_denom(
+    node_url: &str,
+    denom: &str,
+    peggy_address: EthAddress,
+    web30: &Web3,
+) -> Option<EthAddress> {
+    #[cfg(has_peggy_abi)]
+    {
+        let address = peggy_contract(node_url, peggy_address)
+            .cosmos_denom_to_erc20(denom.to_string())
+            .call()
+            .await
+            .expect("Failed to call Peggy.cosmos_denom_to_erc20");
+        if address == ethers::types::Address::zero() {
+            None
+        } else {
+            Some(from_ethers_address(address))
+        }
+    }
+    #[cfg(not(has_peggy_abi))]
+    {
+        let _ = node_url;
+        ethereum_peggy::utils::get_erc20_address_for_denom(denom, peggy_address, web30)
+            .await
+            .expect("Failed to query ERC20 address for denom")
+    }
+}
+
+/// Returns `address`'s balance of the ERC20 at `token`.
+pub async fn erc20_balance(
+    node_url: &str,
+    token: EthAddress,
+    address: EthAddress,
+    web30: &Web3,
+) -> Uint256 {
+    #[cfg(has_test_erc20_abi)]
+    {
+        from_ethers_uint256(
+            test_erc20_contract(node_url, token)
+                .balance_of(to_ethers_address(address))
+                .call()
+                .await
+                .expect("Failed to call TestERC20.balance_of"),
+        )
+    }
+    #[cfg(not(has_test_erc20_abi))]
+    {
+        let _ = node_url;
+        web30
+            .get_erc20_balance(token, address)
+            .await
+            .expect("Failed to query ERC20 balance")
+    }
+}
+
+/// Submits (without waiting for confirmation) an ERC20 `transfer` of
+/// `amount` of `token` to `destination`, using `nonce` so callers can
+/// submit a batch of these before awaiting any of them.
+///
+/// This still goes through `web30` rather than the generated
+/// `abi::test_erc20` binding: submitting a signed transaction through
+/// `ethers` would mean standing up a `SignerMiddleware` alongside the
+/// `web30` client used everywhere else in this crate, which is a bigger
+/// migration than the read-only view calls above warrant on its own.
+pub async fn submit_erc20_transfer(
+    token: EthAddress,
+    destination: EthAddress,
+    amount: Uint256,
+    sender_address: EthAddress,
+    sender_private_key: EthPrivateKey,
+    nonce: Uint256,
+    web30: &Web3,
+) -> Uint256 {
+    use web30::types::SendTxOption;
+
+    web30
+        .erc20_transfer(
+            token,
+            destination,
+            amount,
+            sender_address,
+            sender_private_key,
+            vec![SendTxOption::Nonce(nonce)],
+        )
+        .await
+        .expect("Failed to send ERC20 transfer transaction")
+}
@@ -0,0 +1,210 @@
+//! Native deployment of the Peggy and TestERC20 contracts. Replaces the old
+//! approach of shelling out to `contract-deployer.ts` and scraping its
+//! stdout for addresses: instead we deploy Peggy through a well-known
+//! CREATE2 singleton deployer and TestERC20 with an ordinary `CREATE` from
+//! the deployer key, predict each resulting address ourselves, and confirm
+//! the code actually landed there.
+
+use clarity::Address as EthAddress;
+use clarity::PrivateKey as EthPrivateKey;
+use sha3::{Digest, Keccak256};
+use std::fs;
+use std::time::Duration;
+use web30::client::Web3;
+use web30::types::SendTxOption;
+
+/// The "deterministic deployment proxy", a singleton contract with no
+/// constructor that forwards `CREATE2(salt, init_code)` from any caller. It
+/// lives at the same address on every EVM chain because it's deployed via a
+/// presigned, nonce-zero transaction rather than a normal `CREATE`.
+/// See https://github.com/Arachnid/deterministic-deployment-proxy
+fn singleton_deployer() -> EthAddress {
+    "0x4e59b44847b379578588920cA78FbF26c0B4956C"
+        .parse()
+        .unwrap()
+}
+
+/// The subset of a Truffle/Solidity build artifact we care about.
+#[derive(serde::Deserialize)]
+struct ContractArtifact {
+    bytecode: String,
+}
+
+fn load_init_code(path: &str) -> Vec<u8> {
+    let raw = fs::read_to_string(path)
+        .unwrap_or_else(|_| panic!("Failed to read contract artifact at {}", path));
+    let artifact: ContractArtifact =
+        serde_json::from_str(&raw).expect("Failed to parse contract artifact");
+    clarity::utils::hex_str_to_bytes(artifact.bytecode.trim_start_matches("0x"))
+        .expect("Bad bytecode in contract artifact")
+}
+
+/// Computes `keccak256(0xff ++ deployer ++ salt ++ keccak256(init_code))[12..]`,
+/// the address a `CREATE2` from `deployer` with `salt` and `init_code` will
+/// land at. This lets us know the deployed address before we ever send a
+/// transaction.
+fn predict_create2_address(deployer: EthAddress, salt: [u8; 32], init_code: &[u8]) -> EthAddress {
+    let init_code_hash = Keccak256::digest(init_code);
+    let mut preimage = Vec::with_capacity(1 + 20 + 32 + 32);
+    preimage.push(0xff);
+    preimage.extend_from_slice(deployer.as_bytes());
+    preimage.extend_from_slice(&salt);
+    preimage.extend_from_slice(&init_code_hash);
+    let hash = Keccak256::digest(&preimage);
+    EthAddress::from_slice(&hash[12..]).expect("Keccak256 output is not 32 bytes")
+}
+
+/// Sends `init_code` to the singleton deployer for a `CREATE2` deployment
+/// under `salt`, waits for the deployment to confirm, and asserts the
+/// predicted address actually has code at it.
+async fn create2_deploy(
+    web30: &Web3,
+    deployer_private_key: EthPrivateKey,
+    deployer_address: EthAddress,
+    salt: [u8; 32],
+    init_code: Vec<u8>,
+    timeout: Duration,
+) -> EthAddress {
+    let predicted = predict_create2_address(singleton_deployer(), salt, &init_code);
+
+    let mut payload = salt.to_vec();
+    payload.extend_from_slice(&init_code);
+
+    let txid = web30
+        .send_transaction(
+            singleton_deployer(),
+            payload,
+            0u32.into(),
+            deployer_address,
+            deployer_private_key,
+            vec![],
+        )
+        .await
+        .expect("Failed to send CREATE2 deployment transaction");
+    web30
+        .wait_for_transaction(txid, timeout, None)
+        .await
+        .expect("CREATE2 deployment transaction failed to confirm");
+
+    let code = web30
+        .eth_get_code(predicted)
+        .await
+        .expect("Failed to fetch code at predicted CREATE2 address");
+    assert!(
+        !code.is_empty(),
+        "No code found at predicted CREATE2 address {}, deployment failed",
+        predicted
+    );
+
+    predicted
+}
+
+/// Deploys `init_code` directly from `deployer_private_key` via an ordinary
+/// `CREATE` transaction (`to` the zero address), waits for it to confirm,
+/// and asserts the predicted address actually has code at it.
+///
+/// Unlike `create2_deploy`, the deployed contract's constructor sees
+/// `deployer_address` as `msg.sender` rather than the CREATE2 singleton
+/// proxy - required for contracts like `TestERC20` whose constructor mints
+/// the initial supply to `msg.sender`, since the immutable proxy contract
+/// has no way to move tokens back out.
+async fn deploy_direct(
+    web30: &Web3,
+    deployer_private_key: EthPrivateKey,
+    deployer_address: EthAddress,
+    init_code: Vec<u8>,
+    timeout: Duration,
+) -> EthAddress {
+    let nonce = web30
+        .eth_get_transaction_count(deployer_address)
+        .await
+        .expect("Failed to get deployer nonce");
+    let predicted = clarity::calculate_contract_address(deployer_address, nonce.clone());
+
+    let txid = web30
+        .send_transaction(
+            EthAddress::default(),
+            init_code,
+            0u32.into(),
+            deployer_address,
+            deployer_private_key,
+            vec![SendTxOption::Nonce(nonce)],
+        )
+        .await
+        .expect("Failed to send contract deployment transaction");
+    web30
+        .wait_for_transaction(txid, timeout, None)
+        .await
+        .expect("Contract deployment transaction failed to confirm");
+
+    let code = web30
+        .eth_get_code(predicted)
+        .await
+        .expect("Failed to fetch code at predicted deployment address");
+    assert!(
+        !code.is_empty(),
+        "No code found at predicted deployment address {}, deployment failed",
+        predicted
+    );
+
+    predicted
+}
+
+/// Deploys the Peggy contract deterministically via CREATE2 and a TestERC20
+/// contract directly from `miner_private_key`, using a fixed salt for Peggy
+/// so repeated runs with the same deployer key and artifacts always produce
+/// the same Peggy address.
+pub async fn deploy_contracts(
+    web30: &Web3,
+    miner_private_key: EthPrivateKey,
+    miner_address: EthAddress,
+    peggy_id: &str,
+    timeout: Duration,
+) -> (EthAddress, EthAddress) {
+    // the CREATE2 deploys below all go through this well-known singleton,
+    // which only exists on a chain if something pre-deployed it - confirm
+    // that's true of this test genesis with a clear error message, rather
+    // than letting create2_deploy's own "prediction didn't land" assert
+    // fire and look like our deployment transaction failed
+    let proxy_code = web30
+        .eth_get_code(singleton_deployer())
+        .await
+        .expect("Failed to fetch code at the CREATE2 singleton deployer address");
+    assert!(
+        !proxy_code.is_empty(),
+        "No deterministic deployment proxy found at {}; the test genesis must pre-deploy it, \
+         see https://github.com/Arachnid/deterministic-deployment-proxy",
+        singleton_deployer()
+    );
+
+    let mut peggy_init_code = load_init_code("/peggy/solidity/artifacts/Peggy.json");
+    peggy_init_code.extend_from_slice(&crate::abi::encode_bytes32(peggy_id));
+    let erc20_init_code = load_init_code("/peggy/solidity/artifacts/TestERC20.json");
+
+    let peggy_salt = [0u8; 32];
+
+    info!("Deploying Peggy contract deterministically via CREATE2");
+    let peggy_address = create2_deploy(
+        web30,
+        miner_private_key,
+        miner_address,
+        peggy_salt,
+        peggy_init_code,
+        timeout,
+    )
+    .await;
+    info!("Peggy deployed at {}", peggy_address);
+
+    info!("Deploying TestERC20 contract");
+    let erc20_address = deploy_direct(
+        web30,
+        miner_private_key,
+        miner_address,
+        erc20_init_code,
+        timeout,
+    )
+    .await;
+    info!("TestERC20 deployed at {}", erc20_address);
+
+    (peggy_address, erc20_address)
+}
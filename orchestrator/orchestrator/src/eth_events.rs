@@ -0,0 +1,105 @@
+//! Drives an `eth_newFilter` / `eth_getFilterChanges` loop for Peggy
+//! contract events and exposes the results as a `Stream`, so callers can
+//! `await` a specific event instead of re-polling contract state on a
+//! fixed interval.
+
+use clarity::Address as EthAddress;
+use clarity::Uint256;
+use futures::stream::{self, Stream, StreamExt};
+use std::time::Duration;
+use tokio::time::{delay_for, timeout};
+use web30::client::Web3;
+use web30::types::Log;
+
+/// Keccak256 of `ValsetUpdatedEvent(uint256,address[],uint256[])`, the
+/// signature topic the Peggy contract emits on every validator set update.
+const VALSET_UPDATED_EVENT_SIG: &str =
+    "0xc6d025c076bafcdd040f00632d5e280b3a5188963f110f8c70c4f810184b30f3";
+
+/// A decoded `ValsetUpdatedEvent` log.
+#[derive(Debug, Clone)]
+pub struct ValsetUpdated {
+    pub nonce: Uint256,
+    pub block_number: Uint256,
+}
+
+fn decode_valset_updated(log: &Log) -> ValsetUpdated {
+    // the nonce is the event's only indexed argument, so it lives in the
+    // second topic (the first topic is the event signature itself)
+    let nonce = Uint256::from_bytes_be(&log.topics[1]);
+    ValsetUpdated {
+        nonce,
+        block_number: log.block_number,
+    }
+}
+
+/// Polls `eth_getFilterChanges` on `poll_interval` and yields every
+/// `ValsetUpdatedEvent` the Peggy contract at `peggy_address` emits from
+/// the point the filter is installed onward.
+pub async fn valset_updated_stream(
+    web30: Web3,
+    peggy_address: EthAddress,
+    poll_interval: Duration,
+) -> impl Stream<Item = ValsetUpdated> {
+    let filter_id = web30
+        .eth_new_filter(
+            Some(vec![peggy_address]),
+            Some(vec![VALSET_UPDATED_EVENT_SIG.parse().unwrap()]),
+        )
+        .await
+        .expect("Failed to install ValsetUpdatedEvent filter");
+
+    stream::unfold(
+        (web30, filter_id),
+        move |(web30, filter_id)| async move {
+            loop {
+                let logs = web30.eth_get_filter_changes(filter_id).await;
+                match logs {
+                    Ok(logs) if !logs.is_empty() => {
+                        let events: Vec<ValsetUpdated> =
+                            logs.iter().map(decode_valset_updated).collect();
+                        return Some((stream::iter(events), (web30, filter_id)));
+                    }
+                    Ok(_) => delay_for(poll_interval).await,
+                    Err(e) => {
+                        error!("Error polling ValsetUpdatedEvent filter: {}", e);
+                        delay_for(poll_interval).await;
+                    }
+                }
+            }
+        },
+    )
+    .flatten()
+}
+
+/// Awaits the first `ValsetUpdatedEvent` whose nonce is greater than
+/// `starting_nonce`, replacing the old fixed-interval polling loop that
+/// repeatedly re-read `get_valset_nonce`. Panics with a clear message if no
+/// such event arrives within `deadline`, rather than hanging the test
+/// runner forever on a stuck orchestrator.
+pub async fn wait_for_valset_update(
+    web30: Web3,
+    peggy_address: EthAddress,
+    starting_nonce: Uint256,
+    poll_interval: Duration,
+    deadline: Duration,
+) -> ValsetUpdated {
+    let wait = async {
+        let mut events =
+            Box::pin(valset_updated_stream(web30, peggy_address, poll_interval).await);
+        loop {
+            match events.next().await {
+                Some(event) if event.nonce > starting_nonce => return event,
+                Some(_) => continue,
+                None => panic!("ValsetUpdatedEvent stream ended unexpectedly"),
+            }
+        }
+    };
+
+    timeout(deadline, wait).await.unwrap_or_else(|_| {
+        panic!(
+            "Timed out after {:?} waiting for a ValsetUpdatedEvent past nonce {}",
+            deadline, starting_nonce
+        )
+    })
+}
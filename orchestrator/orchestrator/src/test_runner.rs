@@ -4,8 +4,12 @@
 #[macro_use]
 extern crate log;
 
+pub mod abi;
+pub mod deploy;
+pub mod eth_events;
 pub mod main_loop;
 pub mod tests;
+pub mod utils;
 pub mod valset_relaying;
 
 use actix::Arbiter;
@@ -17,15 +21,15 @@ use cosmos_peggy::send::update_peggy_eth_address;
 use cosmos_peggy::utils::wait_for_cosmos_online;
 use deep_space::coin::Coin;
 use deep_space::private_key::PrivateKey as CosmosPrivateKey;
-use ethereum_peggy::utils::get_valset_nonce;
 use main_loop::orchestrator_main_loop;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::process::Command;
 use std::time::Duration;
-use tokio::time::delay_for;
 
 const TIMEOUT: Duration = Duration::from_secs(30);
+// orchestrators relay on their own schedule, so give them much longer than
+// a single transaction confirmation to notice and act on a request
+const RELAY_TIMEOUT: Duration = Duration::from_secs(300);
 
 /// Ethereum keys are generated for every validator inside
 /// of this testing application and submitted to the blockchain
@@ -100,6 +104,13 @@ async fn main() {
     info!("Waiting for Cosmos chain to come online");
     wait_for_cosmos_online(&contact).await;
 
+    // snapshot this before any fee-consuming Cosmos transactions below, so
+    // we can later assert it actually went down instead of just checking
+    // it's nonzero (validators start with a nonzero genesis balance)
+    let validator_address = keys[0].0.to_public_key().unwrap().to_address();
+    let validator_balance_before =
+        utils::get_cosmos_balance(&test_token_name, validator_address, &contact).await;
+
     // register all validator eth addresses, currently validators can just not do this
     // a full production version of Peggy would refuse to allow validators to enter the pool
     // without registering their address. It would also allow them to delegate their Cosmos addr
@@ -118,66 +129,58 @@ async fn main() {
             .expect("Failed to update Eth address");
     }
 
-    // wait for the orchestrators to finish registering their eth addresses
-    let output = Command::new("npx")
-        .args(&[
-            "ts-node",
-            "/peggy/solidity/contract-deployer.ts",
-            &format!("--cosmos-node={}", COSMOS_NODE),
-            &format!("--eth-node={}", ETH_NODE),
-            &format!("--eth-privkey={:#x}", miner_private_key),
-            &format!("--peggy-id={}", PEGGY_ID),
-            "--contract=/peggy/solidity/artifacts/Peggy.json",
-            "--erc20-contract=/peggy/solidity/artifacts/TestERC20.json",
-            "--test-mode=true",
-        ])
-        .current_dir("/peggy/solidity/")
-        .output()
-        .expect("Failed to deploy contracts!");
-    info!("stdout: {}", String::from_utf8_lossy(&output.stdout));
-    info!("stderr: {}", String::from_utf8_lossy(&output.stderr));
-
-    let mut maybe_peggy_address = None;
-    let mut maybe_contract_address = None;
-    for line in String::from_utf8_lossy(&output.stdout).lines() {
-        if line.contains("Peggy deployed at Address -") {
-            let address_string = line.split('-').last().unwrap();
-            maybe_peggy_address = Some(address_string.trim().parse().unwrap());
-        } else if line.contains("ERC20 deployed at Address -") {
-            let address_string = line.split('-').last().unwrap();
-            maybe_contract_address = Some(address_string.trim().parse().unwrap());
-        }
-    }
-    let peggy_address: EthAddress = maybe_peggy_address.unwrap();
-    let contract_address: EthAddress = maybe_contract_address.unwrap();
+    // deploy Peggy deterministically via CREATE2 and a TestERC20 via a plain
+    // CREATE from the miner key, no Node toolchain or stdout scraping required
+    let (peggy_address, contract_address) =
+        deploy::deploy_contracts(&web30, miner_private_key, miner_address, PEGGY_ID, TIMEOUT).await;
 
     // before we start the orchestrators send them some funds so they can pay
-    // for things
-    for (_c_key, e_key) in keys.iter() {
-        let validator_eth_address = e_key.to_public_key().unwrap();
+    // for things, all funding txs are submitted before any of them are
+    // awaited so this doesn't serialize on confirmation time per validator
+    let validator_eth_addresses: Vec<EthAddress> = keys
+        .iter()
+        .map(|(_c_key, e_key)| e_key.to_public_key().unwrap())
+        .collect();
+    let balance = web30.eth_get_balance(miner_address).await.unwrap();
+    info!(
+        "Sending {} orchestrators 1 eth each to pay for fees, miner has {} WEI",
+        validator_eth_addresses.len(),
+        balance
+    );
+    utils::send_eth_bulk(
+        1_000_000_000_000_000_000u128.into(),
+        &validator_eth_addresses,
+        miner_private_key,
+        &web30,
+        TIMEOUT,
+    )
+    .await;
 
-        let balance = web30.eth_get_balance(miner_address).await.unwrap();
-        info!(
-            "Sending orchestrator 1 eth to pay for fees miner has {} WEI",
-            balance
-        );
-        // send every orchestrator 1 eth to pay for fees
-        let txid = web30
-            .send_transaction(
-                validator_eth_address,
-                Vec::new(),
-                1_000_000_000_000_000_000u128.into(),
-                miner_address,
-                miner_private_key,
-                vec![],
-            )
-            .await
-            .expect("Failed to send Eth to validator {}");
-        web30
-            .wait_for_transaction(txid, TIMEOUT, None)
-            .await
-            .unwrap();
-    }
+    // also give every validator some of the TestERC20 we just deployed, so
+    // it's available to tests that need an Ethereum-side balance to work
+    // with, using the same submit-then-await-all pattern as send_eth_bulk
+    info!(
+        "Airdropping {} test ERC20 to {} validators",
+        contract_address,
+        validator_eth_addresses.len()
+    );
+    utils::airdrop_erc20(
+        contract_address,
+        1_000_000u64.into(),
+        &validator_eth_addresses,
+        miner_private_key,
+        &web30,
+        TIMEOUT,
+    )
+    .await;
+    let airdropped_balance =
+        abi::calls::erc20_balance(ETH_NODE, contract_address, validator_eth_addresses[0], &web30)
+            .await;
+    assert!(
+        airdropped_balance >= 1_000_000u64.into(),
+        "Validator {} did not receive its test ERC20 airdrop",
+        validator_eth_addresses[0]
+    );
 
     // start orchestrators, send them some eth so that they can pay for things
     for (c_key, e_key) in keys.iter() {
@@ -195,9 +198,8 @@ async fn main() {
         ));
     }
 
-    let starting_eth_valset_nonce = get_valset_nonce(peggy_address, miner_address, &web30)
-        .await
-        .expect("Failed to get starting eth valset");
+    let starting_eth_valset_nonce =
+        abi::calls::valset_nonce(ETH_NODE, peggy_address, miner_address, &web30).await;
 
     // now we send a valset request that the orchestrators will pick up on
     // in this case we send it as the first validator because they can pay the fee
@@ -206,23 +208,74 @@ async fn main() {
         .await
         .expect("Failed to send valset request");
 
-    let mut current_eth_valset_nonce = get_valset_nonce(peggy_address, miner_address, &web30)
-        .await
-        .expect("Failed to get current eth valset");
     info!(
         "Our starting valset is {}, waiting for orchestrators to update it",
-        current_eth_valset_nonce,
+        starting_eth_valset_nonce,
     );
-    while starting_eth_valset_nonce == current_eth_valset_nonce {
-        info!("Validator set is not yet updated, waiting");
-        current_eth_valset_nonce = get_valset_nonce(peggy_address, miner_address, &web30)
-            .await
-            .expect("Failed to get current eth valset");
-        delay_for(Duration::from_secs(10)).await;
-    }
+    let updated_valset = eth_events::wait_for_valset_update(
+        web30.clone(),
+        peggy_address,
+        starting_eth_valset_nonce,
+        Duration::from_secs(1),
+        RELAY_TIMEOUT,
+    )
+    .await;
+
+    info!(
+        "Validator set successfully updated to nonce {}!",
+        updated_valset.nonce
+    );
+
+    let cosmos_valset = valset_relaying::Valset {
+        members: keys
+            .iter()
+            .map(|(_c_key, e_key)| (e_key.to_public_key().unwrap(), 1u64))
+            .collect(),
+    };
+    let mut valset_cache = valset_relaying::ValsetCache::new(16);
+    valset_relaying::verify_valset_update(
+        ETH_NODE,
+        &web30,
+        peggy_address,
+        miner_address,
+        PEGGY_ID,
+        updated_valset.nonce,
+        // this is the first valset update in the test, so it has no
+        // already-verified predecessor to check against
+        None,
+        cosmos_valset,
+        &mut valset_cache,
+    )
+    .await;
+    info!("On-chain validator set matches the Cosmos-side valset!");
 
-    info!("Validator set successfully updated!");
+    info!("Starting ERC20 happy path test");
+    tests::erc20_happy_path::erc20_happy_path_test(
+        ETH_NODE,
+        &web30,
+        &contact,
+        keys[0].0,
+        peggy_address,
+        keys[1].1.to_public_key().unwrap(),
+        test_token_name.clone(),
+        "Foo Token".to_string(),
+        "FOO".to_string(),
+        6,
+        100u128,
+        RELAY_TIMEOUT,
+    )
+    .await;
+    info!("ERC20 happy path test passed!");
 
-    // TODO verify that a valset update has been performed
-    // TODO verify that some transactions have passed etc etc
+    // verify that this validator's Cosmos transactions above (its Eth address
+    // registration, the valset request and the happy path sends) did not all
+    // fail: what they spent must have brought the balance we snapshotted at
+    // startup down. A decrease does not tell which of them went through
+    utils::assert_cosmos_balance_decreased(
+        &test_token_name,
+        validator_address,
+        &contact,
+        validator_balance_before,
+    )
+    .await;
 }
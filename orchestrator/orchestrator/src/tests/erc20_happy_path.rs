@@ -0,0 +1,111 @@
+//! Cosmos-originated ERC20 deploy + round trip. Registers a Cosmos denom's
+//! metadata, waits for the orchestrators to observe the resulting
+//! ERC20-deploy event and deploy a representative ERC20 on Ethereum, sends
+//! some of that denom across via `SendToEth`, and confirms it lands in the
+//! recipient's ERC20 balance.
+
+use crate::abi;
+use clarity::Address as EthAddress;
+use contact::client::Contact;
+use cosmos_peggy::send::send_deploy_erc20_request;
+use cosmos_peggy::send::send_to_eth;
+use deep_space::coin::Coin;
+use deep_space::private_key::PrivateKey as CosmosPrivateKey;
+use std::time::Duration;
+use std::time::Instant;
+use tokio::time::delay_for;
+use web30::client::Web3;
+
+/// How long we're willing to wait for the orchestrators to notice the
+/// deploy request and the matching ERC20 to show up on chain.
+const ERC20_DEPLOY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Drives the full deploy-event -> mint/transfer path: register `denom`'s
+/// metadata on Cosmos, wait for its ERC20 counterpart to appear at
+/// `peggy_address`, send `amount` of it to `eth_dest` on Ethereum, and
+/// assert the transfer lands.
+#[allow(clippy::too_many_arguments)]
+pub async fn erc20_happy_path_test(
+    node_url: &str,
+    web30: &Web3,
+    contact: &Contact,
+    sender: CosmosPrivateKey,
+    peggy_address: EthAddress,
+    eth_dest: EthAddress,
+    denom: String,
+    denom_name: String,
+    denom_symbol: String,
+    denom_decimals: u32,
+    amount: u128,
+    timeout: Duration,
+) {
+    info!(
+        "Registering Cosmos denom {} ({}, {} decimals) for ERC20 deploy",
+        denom, denom_symbol, denom_decimals
+    );
+    let fee = Coin {
+        denom: denom.clone(),
+        amount: 1u8.into(),
+    };
+    send_deploy_erc20_request(
+        contact,
+        sender,
+        denom.clone(),
+        denom_name,
+        denom_symbol,
+        denom_decimals,
+        fee.clone(),
+        None,
+        None,
+        None,
+    )
+    .await
+    .expect("Failed to send ERC20 deploy request");
+
+    info!("Waiting for orchestrators to deploy the ERC20 representing {}", denom);
+    let start = Instant::now();
+    let erc20_address = loop {
+        if let Some(address) =
+            abi::calls::erc20_for_denom(node_url, &denom, peggy_address, web30).await
+        {
+            break address;
+        }
+        assert!(
+            Instant::now() - start < ERC20_DEPLOY_TIMEOUT,
+            "Timed out waiting for ERC20 deploy event for {}",
+            denom
+        );
+        delay_for(Duration::from_secs(5)).await;
+    };
+    info!("{} deployed as ERC20 at {}", denom, erc20_address);
+
+    // SendToEth requires the fee denom to match the amount denom
+    let send_amount = Coin {
+        denom: denom.clone(),
+        amount: amount.into(),
+    };
+    let send_fee = Coin {
+        denom,
+        amount: 1u8.into(),
+    };
+    info!("Sending {} to {} on Ethereum", send_amount.amount, eth_dest);
+    send_to_eth(contact, sender, eth_dest, send_amount, send_fee, None, None, None)
+        .await
+        .expect("Failed to send SendToEth message");
+
+    info!("Waiting for the transfer to relay and land in {}'s ERC20 balance", eth_dest);
+    let start = Instant::now();
+    loop {
+        let balance = abi::calls::erc20_balance(node_url, erc20_address, eth_dest, web30).await;
+        if balance >= amount.into() {
+            info!("{} received {} of the bridged ERC20", eth_dest, balance);
+            break;
+        }
+        assert!(
+            Instant::now() - start < timeout,
+            "Timed out waiting for bridged ERC20 balance to arrive at {}",
+            eth_dest
+        );
+        delay_for(Duration::from_secs(5)).await;
+    }
+}
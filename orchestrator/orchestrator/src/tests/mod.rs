@@ -0,0 +1,5 @@
+//! End-to-end test scenarios exercised by the test runner, each one driving
+//! a specific path through the orchestrators and contracts rather than the
+//! baseline valset-relaying flow in `main()`.
+
+pub mod erc20_happy_path;
@@ -0,0 +1,143 @@
+//! Miscellaneous helpers shared across test scenarios: bulk funding of
+//! validator Ethereum addresses, ERC20 airdrops, and Cosmos balance checks.
+//! These exist mainly to get test setup (currently dominated by sequential
+//! `wait_for_transaction` calls) running in parallel.
+
+use crate::abi;
+use clarity::Address as EthAddress;
+use clarity::PrivateKey as EthPrivateKey;
+use clarity::Uint256;
+use contact::client::Contact;
+use deep_space::address::Address as CosmosAddress;
+use deep_space::coin::Coin;
+use futures::future::join_all;
+use std::time::Duration;
+use web30::client::Web3;
+use web30::types::SendTxOption;
+
+/// Sends `amount` wei of Ether to each address in `destinations` from the
+/// `sender` key, submitting every transaction before waiting on any of
+/// them. The nonce is read once and incremented locally per transaction, so
+/// the sends don't have to be serialized through `wait_for_transaction`.
+pub async fn send_eth_bulk(
+    amount: Uint256,
+    destinations: &[EthAddress],
+    sender_private_key: EthPrivateKey,
+    web30: &Web3,
+    timeout: Duration,
+) {
+    let sender_address = sender_private_key.to_public_key().unwrap();
+    let mut nonce = web30
+        .eth_get_transaction_count(sender_address)
+        .await
+        .expect("Failed to get sender nonce");
+
+    let mut txids = Vec::new();
+    for destination in destinations {
+        let txid = web30
+            .send_transaction(
+                *destination,
+                Vec::new(),
+                amount.clone(),
+                sender_address,
+                sender_private_key,
+                vec![SendTxOption::Nonce(nonce.clone())],
+            )
+            .await
+            .expect("Failed to send bulk Eth funding transaction");
+        txids.push(txid);
+        nonce += 1u8.into();
+    }
+
+    join_all(
+        txids
+            .into_iter()
+            .map(|txid| web30.wait_for_transaction(txid, timeout, None)),
+    )
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()
+    .expect("A bulk Eth funding transaction failed to confirm");
+}
+
+/// Distributes `amount` of `token` to each address in `destinations`, built
+/// the same way as [`send_eth_bulk`]: all transfers are submitted first,
+/// then awaited together.
+pub async fn airdrop_erc20(
+    token: EthAddress,
+    amount: Uint256,
+    destinations: &[EthAddress],
+    sender_private_key: EthPrivateKey,
+    web30: &Web3,
+    timeout: Duration,
+) {
+    let sender_address = sender_private_key.to_public_key().unwrap();
+    let mut nonce = web30
+        .eth_get_transaction_count(sender_address)
+        .await
+        .expect("Failed to get sender nonce");
+
+    let mut txids = Vec::new();
+    for destination in destinations {
+        let txid = abi::calls::submit_erc20_transfer(
+            token,
+            *destination,
+            amount.clone(),
+            sender_address,
+            sender_private_key,
+            nonce.clone(),
+            web30,
+        )
+        .await;
+        txids.push(txid);
+        nonce += 1u8.into();
+    }
+
+    join_all(
+        txids
+            .into_iter()
+            .map(|txid| web30.wait_for_transaction(txid, timeout, None)),
+    )
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()
+    .expect("An ERC20 airdrop transaction failed to confirm");
+}
+
+/// Returns `address`'s balance of `denom` on Cosmos, or zero if it holds
+/// none at all.
+pub async fn get_cosmos_balance(denom: &str, address: CosmosAddress, contact: &Contact) -> Uint256 {
+    let balances: Vec<Coin> = contact
+        .get_balances(address)
+        .await
+        .expect("Failed to get Cosmos balances");
+    balances
+        .into_iter()
+        .find(|c| c.denom == denom)
+        .map(|c| c.amount)
+        .unwrap_or_else(|| 0u8.into())
+}
+
+/// Asserts that `address`'s balance of `denom` is lower than
+/// `balance_before`, the value returned by an earlier [`get_cosmos_balance`]
+/// call. Validators hold a nonzero genesis balance of the test denom
+/// before any of this test's activity, so a bare "balance is nonzero"
+/// check would pass even if none of the fee-consuming Cosmos transactions
+/// this test sends actually went through; a decrease only happens if they
+/// did.
+pub async fn assert_cosmos_balance_decreased(
+    denom: &str,
+    address: CosmosAddress,
+    contact: &Contact,
+    balance_before: Uint256,
+) {
+    let balance_after = get_cosmos_balance(denom, address, contact).await;
+    assert!(
+        balance_after < balance_before,
+        "{}'s {} balance did not decrease ({} before, {} after), Cosmos transactions may not have gone through",
+        address,
+        denom,
+        balance_before,
+        balance_after,
+    );
+}
@@ -0,0 +1,224 @@
+//! Verifies that a valset update relayed to Ethereum actually matches the
+//! validator set the orchestrators signed on Cosmos, rather than just
+//! trusting that the on-chain nonce moved. Results are cached per nonce so
+//! a test asserting on several updates in a row doesn't re-query the chain
+//! for ones it's already verified.
+
+use clarity::Address as EthAddress;
+use clarity::Uint256;
+use sha3::{Digest, Keccak256};
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use web30::client::Web3;
+
+/// The Cosmos-side validator set as signed by the orchestrators: each
+/// entry is an Ethereum signing address and its voting power.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Valset {
+    pub members: Vec<(EthAddress, u64)>,
+}
+
+/// A verified valset, kept around so repeated lookups for the same nonce
+/// don't have to hit the chain again.
+#[derive(Debug, Clone)]
+struct CachedValset {
+    valset: Valset,
+    checkpoint: [u8; 32],
+}
+
+/// A tiny fixed-capacity LRU cache keyed by valset nonce. Just large enough
+/// to avoid re-verifying the handful of updates a single test performs;
+/// nothing fancier is needed here.
+pub struct ValsetCache {
+    capacity: usize,
+    entries: HashMap<Uint256, CachedValset>,
+    order: VecDeque<Uint256>,
+}
+
+impl ValsetCache {
+    pub fn new(capacity: usize) -> Self {
+        ValsetCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&mut self, nonce: &Uint256) -> Option<&CachedValset> {
+        if self.entries.contains_key(nonce) {
+            self.order.retain(|n| n != nonce);
+            self.order.push_back(nonce.clone());
+        }
+        self.entries.get(nonce)
+    }
+
+    /// Returns the valset a previous call to [`verify_valset_update`]
+    /// verified for `nonce`, if it's still in the cache. Lets a later
+    /// update check that its predecessor checkpoint was built from the
+    /// valset that was actually verified on chain.
+    pub fn get_verified_valset(&mut self, nonce: &Uint256) -> Option<Valset> {
+        self.get(nonce).map(|cached| cached.valset.clone())
+    }
+
+    fn insert(&mut self, nonce: Uint256, cached: CachedValset) {
+        if !self.entries.contains_key(&nonce) && self.entries.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.order.retain(|n| n != &nonce);
+        self.order.push_back(nonce.clone());
+        self.entries.insert(nonce, cached);
+    }
+}
+
+/// Encodes `v` as a 32 byte, big-endian `uint256` ABI word.
+fn abi_uint256(v: &Uint256) -> [u8; 32] {
+    let mut buf = [0u8; 32];
+    let be = v.to_bytes_be();
+    let start = 32 - be.len();
+    buf[start..].copy_from_slice(&be);
+    buf
+}
+
+/// Left-zero-pads a 20 byte address into a 32 byte ABI word, the encoding
+/// Solidity's `address` arguments use.
+fn abi_address(address: &EthAddress) -> [u8; 32] {
+    let mut buf = [0u8; 32];
+    buf[12..].copy_from_slice(address.as_bytes());
+    buf
+}
+
+/// Computes the checkpoint hash the Peggy contract's `makeCheckpoint`
+/// produces for a given valset and nonce, so it can be compared against
+/// the checkpoint the contract actually has on file.
+///
+/// This mirrors `keccak256(abi.encode(_peggyId, "checkpoint", _valsetNonce,
+/// _validators, _powers))`: `_validators` and `_powers` are dynamic
+/// `address[]`/`uint256[]` arguments, so the ABI head only carries their
+/// byte offsets into the tail, where each array is itself length-prefixed
+/// and element-padded to a 32 byte word.
+fn compute_checkpoint(peggy_id: &str, nonce: &Uint256, valset: &Valset) -> [u8; 32] {
+    let member_count = valset.members.len();
+    // 5 head words: peggyId, method name, nonce, validators offset, powers offset
+    let head_size = 5 * 32;
+    let validators_offset = head_size;
+    // one length word plus one word per address
+    let validators_size = 32 + member_count * 32;
+    let powers_offset = validators_offset + validators_size;
+
+    let mut encoded = Vec::with_capacity(head_size + validators_size + 32 + member_count * 32);
+    encoded.extend_from_slice(&crate::abi::encode_bytes32(peggy_id));
+    encoded.extend_from_slice(&crate::abi::encode_bytes32("checkpoint"));
+    encoded.extend_from_slice(&abi_uint256(nonce));
+    encoded.extend_from_slice(&abi_uint256(&(validators_offset as u64).into()));
+    encoded.extend_from_slice(&abi_uint256(&(powers_offset as u64).into()));
+
+    encoded.extend_from_slice(&abi_uint256(&(member_count as u64).into()));
+    for (address, _power) in &valset.members {
+        encoded.extend_from_slice(&abi_address(address));
+    }
+    encoded.extend_from_slice(&abi_uint256(&(member_count as u64).into()));
+    for (_address, power) in &valset.members {
+        encoded.extend_from_slice(&abi_uint256(&(*power).into()));
+    }
+
+    Keccak256::digest(&encoded).into()
+}
+
+/// Confirms that the Peggy contract's current on-chain checkpoint matches
+/// the checkpoint implied by the Cosmos-side `valset` the orchestrators
+/// signed for `nonce`, panicking on mismatch. Only the contract's latest
+/// checkpoint is read, so an uncached `nonce` that is not the latest one
+/// relayed fails this check. Verifications are cached
+/// by nonce in `cache` so a later update can cheaply assert that the
+/// correct predecessor checkpoint was used: pass the nonce this update
+/// claims to have followed as `predecessor_nonce` (`None` for the very
+/// first update, which has no predecessor to check) and it's asserted to
+/// already be present in `cache`, i.e. to have itself been verified.
+#[allow(clippy::too_many_arguments)]
+pub async fn verify_valset_update(
+    node_url: &str,
+    web30: &Web3,
+    peggy_address: EthAddress,
+    caller_address: EthAddress,
+    peggy_id: &str,
+    nonce: Uint256,
+    predecessor_nonce: Option<Uint256>,
+    cosmos_valset: Valset,
+    cache: &mut ValsetCache,
+) {
+    if let Some(predecessor) = &predecessor_nonce {
+        assert!(
+            cache.get_verified_valset(predecessor).is_some(),
+            "Valset nonce {} claims predecessor {} but that predecessor was never verified",
+            nonce,
+            predecessor
+        );
+    }
+
+    let expected_checkpoint = compute_checkpoint(peggy_id, &nonce, &cosmos_valset);
+
+    if let Some(cached) = cache.get(&nonce) {
+        assert_eq!(
+            cached.checkpoint, expected_checkpoint,
+            "Cached checkpoint for valset nonce {} does not match the Cosmos-side valset",
+            nonce
+        );
+        return;
+    }
+
+    let onchain_checkpoint =
+        crate::abi::calls::checkpoint(node_url, peggy_address, caller_address, web30).await;
+    assert_eq!(
+        onchain_checkpoint, expected_checkpoint,
+        "On-chain checkpoint for valset nonce {} does not match the Cosmos-side valset",
+        nonce
+    );
+
+    cache.insert(
+        nonce,
+        CachedValset {
+            valset: cosmos_valset,
+            checkpoint: expected_checkpoint,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pins `compute_checkpoint` against `keccak256(abi.encode(bytes32,
+    /// bytes32, uint256, address[], uint256[]))` for a fixed set of inputs,
+    /// computed independently via `ethabi`'s ABI encoder. Catches any
+    /// regression back to a naive concatenation-based preimage.
+    #[test]
+    fn compute_checkpoint_matches_abi_encode() {
+        let valset = Valset {
+            members: vec![
+                (
+                    "0x1111111111111111111111111111111111111111"
+                        .parse()
+                        .unwrap(),
+                    1000,
+                ),
+                (
+                    "0x2222222222222222222222222222222222222222"
+                        .parse()
+                        .unwrap(),
+                    2000,
+                ),
+            ],
+        };
+
+        let checkpoint = compute_checkpoint("foo", &1u64.into(), &valset);
+
+        let expected: [u8; 32] = [
+            0x51, 0xe7, 0x86, 0xc6, 0x2f, 0x55, 0xd6, 0xb2, 0xf8, 0x7a, 0xf8, 0x72, 0x69, 0x43,
+            0xf2, 0xca, 0xb2, 0xdc, 0x4d, 0x55, 0x81, 0xc8, 0xb0, 0x01, 0xaa, 0x14, 0xef, 0x77,
+            0x7a, 0x0d, 0x32, 0x22,
+        ];
+        assert_eq!(checkpoint, expected);
+    }
+}